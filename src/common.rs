@@ -1,6 +1,7 @@
 use std::{
-    io::{Cursor, Seek, SeekFrom, Write},
-    path::{Path, StripPrefixError},
+    collections::HashMap,
+    io::{self, Cursor, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf, StripPrefixError},
     string::FromUtf8Error,
     time::SystemTime,
 };
@@ -148,17 +149,13 @@ impl Header {
         let path = path.as_ref();
         let metadata = std::fs::metadata(path).map_err(|_| FileParseError::Metadata)?;
 
-        let name = path.file_name().ok_or(FileParseError::EmptyName)?;
-        FILENAME
-            .checked_sub(name.len())
-            .ok_or(LengthExceededError::Name)?;
-
-        let name = name.to_string_lossy().to_string();
+        let name = path
+            .file_name()
+            .ok_or(FileParseError::EmptyName)?
+            .to_string_lossy()
+            .to_string();
 
         let size = metadata.len();
-        let size_str = size.to_string();
-        SIZE.checked_sub(size_str.len())
-            .ok_or(LengthExceededError::Size)?;
 
         let mtime = metadata
             .modified()
@@ -166,14 +163,33 @@ impl Header {
             .duration_since(SystemTime::UNIX_EPOCH)
             .map_err(|_| FileParseError::UnixEpoch)?
             .as_secs();
-        let mtime_str = mtime.to_string();
-        MTIME
-            .checked_sub(mtime_str.len())
-            .ok_or(LengthExceededError::Mtime)?;
 
         let prefix = path
             .parent()
             .map_or(String::from(""), |p| p.to_string_lossy().to_string());
+
+        Ok(Header::new(name, size, mtime, prefix)?)
+    }
+
+    /// Builds an archive metadata entry from explicit field values, rather than from a real
+    /// file's metadata. Useful for synthesized content, such as a generated SQL dump or an
+    /// in-memory config, that has no backing path on disk.
+    pub fn new(
+        name: String,
+        size: u64,
+        mtime: u64,
+        prefix: String,
+    ) -> Result<Header, LengthExceededError> {
+        FILENAME
+            .checked_sub(name.len())
+            .ok_or(LengthExceededError::Name)?;
+        let size_str = size.to_string();
+        SIZE.checked_sub(size_str.len())
+            .ok_or(LengthExceededError::Size)?;
+        let mtime_str = mtime.to_string();
+        MTIME
+            .checked_sub(mtime_str.len())
+            .ok_or(LengthExceededError::Mtime)?;
         PREFIX
             .checked_sub(prefix.len())
             .ok_or(LengthExceededError::Prefix)?;
@@ -189,14 +205,61 @@ impl Header {
         bytes.seek(SeekFrom::Start(MTIME_END as u64)).unwrap();
         bytes.write_all(prefix.as_bytes()).unwrap();
 
-        let bytes = bytes.into_inner();
-
         Ok(Header {
             name,
             size,
             mtime,
             prefix,
-            bytes,
+            bytes: bytes.into_inner(),
         })
     }
+
+    /// Overwrites the recorded size of this entry, updating both the `size` field and its
+    /// corresponding region in `bytes`. Useful when a header is built ahead of its data, e.g. for
+    /// a stream whose length isn't known until it has been fully read.
+    pub fn set_size(&mut self, size: u64) -> Result<(), LengthExceededError> {
+        let size_str = size.to_string();
+        SIZE.checked_sub(size_str.len())
+            .ok_or(LengthExceededError::Size)?;
+        self.bytes[SIZE_BEGIN..SIZE_END].fill(0);
+        self.bytes[SIZE_BEGIN..SIZE_BEGIN + size_str.len()].copy_from_slice(size_str.as_bytes());
+        self.size = size;
+        Ok(())
+    }
+}
+
+/// The key an entry is recorded under in a checksum sidecar: its full path inside the archive.
+pub(crate) fn entry_name(header: &Header) -> String {
+    [&header.prefix, &header.name]
+        .iter()
+        .collect::<PathBuf>()
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Parses a checksum sidecar into a map of entry name to recorded digest. The format is one
+/// `name\tdigest` pair per line, mirroring tools like `sha256sum`.
+pub(crate) fn read_manifest<M: Read>(mut manifest: M) -> io::Result<HashMap<String, String>> {
+    let mut contents = String::new();
+    manifest.read_to_string(&mut contents)?;
+    Ok(contents
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(name, digest)| (name.to_string(), digest.to_string()))
+        .collect())
+}
+
+/// A discrepancy found by `Reader::verify` between an archive entry and its checksum sidecar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyError {
+    /// The entry's recomputed digest doesn't match the one recorded in the sidecar.
+    Mismatch {
+        /// Full path of the entry inside the archive.
+        name: String,
+    },
+    /// The sidecar has no digest recorded for this entry.
+    Missing {
+        /// Full path of the entry inside the archive.
+        name: String,
+    },
 }
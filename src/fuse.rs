@@ -0,0 +1,277 @@
+//! Read-only FUSE mount of a `.wpress` archive. Requires the `fuse` feature.
+//!
+//! The inode/name tree is built once at mount time by walking `Reader::headers`, splitting each
+//! entry's `prefix` into directory components and grouping entries underneath them. Reads are
+//! served by seeking the backing file to the entry's data offset and copying bytes on demand, so
+//! an archive never needs to be extracted to inspect a single file.
+use crate::{Header, Reader, common::HEADER_SIZE};
+use fuser::{
+    FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry,
+    Request,
+};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+    time::{Duration, UNIX_EPOCH},
+};
+
+const TTL: Duration = Duration::from_secs(1);
+const ROOT_INO: u64 = 1;
+
+enum Node {
+    Directory { children: HashMap<String, u64> },
+    File { header_index: usize, data_offset: u64 },
+}
+
+struct Inode {
+    parent: u64,
+    node: Node,
+}
+
+/// Read-only FUSE filesystem backed by a `Reader<File>`'s headers.
+pub struct ArchiveFs {
+    reader: Reader<File>,
+    inodes: HashMap<u64, Inode>,
+    next_ino: u64,
+}
+
+impl ArchiveFs {
+    /// Builds the inode tree once from the reader's headers, without reading any file contents.
+    pub fn new(reader: Reader<File>) -> ArchiveFs {
+        let mut inodes = HashMap::new();
+        inodes.insert(
+            ROOT_INO,
+            Inode {
+                parent: ROOT_INO,
+                node: Node::Directory {
+                    children: HashMap::new(),
+                },
+            },
+        );
+        let mut fs = ArchiveFs {
+            reader,
+            inodes,
+            next_ino: ROOT_INO + 1,
+        };
+
+        let headers = fs.reader.headers().to_vec();
+        let mut offset = 0u64;
+        for (index, header) in headers.iter().enumerate() {
+            let data_offset = offset + HEADER_SIZE as u64;
+            offset = data_offset + header.size;
+
+            let dir_ino = fs.ensure_dir(&header.prefix);
+            fs.insert_child(
+                dir_ino,
+                header.name.clone(),
+                Node::File {
+                    header_index: index,
+                    data_offset,
+                },
+            );
+        }
+        fs
+    }
+
+    fn ensure_dir(&mut self, prefix: &str) -> u64 {
+        let mut parent = ROOT_INO;
+        for component in Path::new(prefix)
+            .components()
+            .filter_map(|c| c.as_os_str().to_str())
+        {
+            parent = match self.child(parent, component) {
+                Some(ino) => ino,
+                None => self.insert_child(
+                    parent,
+                    component.to_string(),
+                    Node::Directory {
+                        children: HashMap::new(),
+                    },
+                ),
+            };
+        }
+        parent
+    }
+
+    fn child(&self, parent: u64, name: &str) -> Option<u64> {
+        match &self.inodes.get(&parent)?.node {
+            Node::Directory { children } => children.get(name).copied(),
+            Node::File { .. } => None,
+        }
+    }
+
+    fn insert_child(&mut self, parent: u64, name: String, node: Node) -> u64 {
+        let ino = self.next_ino;
+        self.next_ino += 1;
+        self.inodes.insert(ino, Inode { parent, node });
+        if let Some(Inode {
+            node: Node::Directory { children },
+            ..
+        }) = self.inodes.get_mut(&parent)
+        {
+            children.insert(name, ino);
+        }
+        ino
+    }
+
+    fn attr(&self, ino: u64) -> Option<FileAttr> {
+        match &self.inodes.get(&ino)?.node {
+            Node::Directory { .. } => Some(directory_attr(ino)),
+            Node::File { header_index, .. } => {
+                Some(file_attr(ino, &self.reader.headers()[*header_index]))
+            }
+        }
+    }
+}
+
+fn directory_attr(ino: u64) -> FileAttr {
+    FileAttr {
+        ino,
+        size: 0,
+        blocks: 0,
+        atime: UNIX_EPOCH,
+        mtime: UNIX_EPOCH,
+        ctime: UNIX_EPOCH,
+        crtime: UNIX_EPOCH,
+        kind: FileType::Directory,
+        perm: 0o555,
+        nlink: 2,
+        uid: 0,
+        gid: 0,
+        rdev: 0,
+        blksize: 512,
+        flags: 0,
+    }
+}
+
+fn file_attr(ino: u64, header: &Header) -> FileAttr {
+    let mtime = UNIX_EPOCH + Duration::from_secs(header.mtime);
+    FileAttr {
+        ino,
+        size: header.size,
+        blocks: header.size.div_ceil(512),
+        atime: mtime,
+        mtime,
+        ctime: mtime,
+        crtime: mtime,
+        kind: FileType::RegularFile,
+        perm: 0o444,
+        nlink: 1,
+        uid: 0,
+        gid: 0,
+        rdev: 0,
+        blksize: 512,
+        flags: 0,
+    }
+}
+
+impl Filesystem for ArchiveFs {
+    fn lookup(&mut self, _req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let Some(name) = name.to_str() else {
+            reply.error(libc::ENOENT);
+            return;
+        };
+        match self
+            .child(parent, name)
+            .and_then(|ino| self.attr(ino).map(|attr| (ino, attr)))
+        {
+            Some((_, attr)) => reply.entry(&TTL, &attr, 0),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request, ino: u64, _fh: Option<u64>, reply: ReplyAttr) {
+        match self.attr(ino) {
+            Some(attr) => reply.attr(&TTL, &attr),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let Some(Inode {
+            node: Node::File {
+                header_index,
+                data_offset,
+            },
+            ..
+        }) = self.inodes.get(&ino)
+        else {
+            reply.error(libc::ENOENT);
+            return;
+        };
+        let (header_size, start) = (self.reader.headers()[*header_index].size, *data_offset);
+
+        let remaining = header_size.saturating_sub(offset as u64);
+        let to_read = (size as u64).min(remaining) as usize;
+        let mut buf = vec![0u8; to_read];
+        let file = self.reader.inner_mut();
+        if file.seek(SeekFrom::Start(start + offset as u64)).is_err()
+            || file.read_exact(&mut buf).is_err()
+        {
+            reply.error(libc::EIO);
+            return;
+        }
+        reply.data(&buf);
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let Some(inode) = self.inodes.get(&ino) else {
+            reply.error(libc::ENOENT);
+            return;
+        };
+        let Node::Directory { children } = &inode.node else {
+            reply.error(libc::ENOTDIR);
+            return;
+        };
+
+        let mut entries = vec![
+            (ino, FileType::Directory, ".".to_string()),
+            (inode.parent, FileType::Directory, "..".to_string()),
+        ];
+        for (name, &child_ino) in children {
+            let kind = match &self.inodes[&child_ino].node {
+                Node::Directory { .. } => FileType::Directory,
+                Node::File { .. } => FileType::RegularFile,
+            };
+            entries.push((child_ino, kind, name.clone()));
+        }
+
+        for (i, (child_ino, kind, name)) in entries.into_iter().enumerate().skip(offset as usize) {
+            if reply.add(child_ino, (i + 1) as i64, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+}
+
+/// Mounts `reader` as a read-only filesystem at `mountpoint`, blocking the calling thread until
+/// the filesystem is unmounted.
+pub fn mount<P: AsRef<Path>>(reader: Reader<File>, mountpoint: P) -> std::io::Result<()> {
+    let fs = ArchiveFs::new(reader);
+    fuser::mount2(
+        fs,
+        mountpoint,
+        &[MountOption::RO, MountOption::FSName("wpress".to_string())],
+    )
+}
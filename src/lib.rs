@@ -1,23 +1,198 @@
 mod common;
+#[cfg(feature = "fuse")]
+mod fuse;
 mod reader;
 mod writer;
 
-pub use crate::common::{BlockParseError, FileParseError, Header, LengthExceededError};
-pub use crate::reader::Reader;
+pub use crate::common::{BlockParseError, FileParseError, Header, LengthExceededError, VerifyError};
+#[cfg(feature = "fuse")]
+pub use crate::fuse::{mount, ArchiveFs};
+pub use crate::reader::{Entries, Reader};
 pub use crate::writer::Writer;
 
 #[cfg(test)]
 mod tests {
-    use std::{error::Error, fs::remove_file};
+    use std::{
+        error::Error,
+        fs::{self, remove_dir_all, remove_file, write},
+        io::{Cursor, Read},
+        time::{Duration, UNIX_EPOCH},
+    };
 
     use super::*;
 
     #[test]
     fn create_archive() -> Result<(), Box<dyn Error>> {
-        let mut w = Writer::new("tests/writer_output.wpress")?;
+        let mut w = Writer::create("tests/writer_output.wpress")?;
         w.add("tests/writer")?;
         w.write()?;
         remove_file("tests/writer_output.wpress")?;
         Ok(())
     }
+
+    #[test]
+    fn entries_stream_headers_and_data_in_order() -> Result<(), Box<dyn Error>> {
+        let mut archive = Vec::new();
+        let mut w = Writer::new(&mut archive);
+        w.add_data(
+            Header::new("a.txt".into(), 5, 0, "".into())?,
+            Cursor::new(b"hello".to_vec()),
+        );
+        w.add_data(
+            Header::new("b.txt".into(), 5, 0, "".into())?,
+            Cursor::new(b"world".to_vec()),
+        );
+        w.write()?;
+
+        let mut r = Reader::new(Cursor::new(archive))?;
+        let mut entries = r.entries();
+
+        let (header, mut data) = entries.next().unwrap()?;
+        assert_eq!(header.name, "a.txt");
+        let mut buf = String::new();
+        data.read_to_string(&mut buf)?;
+        assert_eq!(buf, "hello");
+
+        let (header, mut data) = entries.next().unwrap()?;
+        assert_eq!(header.name, "b.txt");
+        let mut buf = String::new();
+        data.read_to_string(&mut buf)?;
+        assert_eq!(buf, "world");
+
+        assert!(entries.next().is_none());
+        Ok(())
+    }
+
+    fn single_entry_archive(
+        name: &str,
+        data: &[u8],
+        mtime: u64,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut archive = Vec::new();
+        let mut w = Writer::new(&mut archive);
+        w.add_data(
+            Header::new(name.to_string(), data.len() as u64, mtime, "".into())?,
+            Cursor::new(data.to_vec()),
+        );
+        w.write()?;
+        Ok(archive)
+    }
+
+    #[test]
+    fn preserve_mtime_restores_header_mtime_on_extraction() -> Result<(), Box<dyn Error>> {
+        let mtime = 1_000_000_000;
+        let archive = single_entry_archive("a.txt", b"hello", mtime)?;
+        let dest = std::env::temp_dir().join("wpress_oxide_test_preserve_mtime");
+        let _ = remove_dir_all(&dest);
+
+        let mut r = Reader::new(Cursor::new(archive))?;
+        r.set_preserve_mtime(true);
+        r.extract_to(&dest)?;
+
+        let modified = fs::metadata(dest.join("a.txt"))?.modified()?;
+        assert_eq!(modified, UNIX_EPOCH + Duration::from_secs(mtime));
+
+        remove_dir_all(&dest)?;
+        Ok(())
+    }
+
+    #[test]
+    fn overwrite_false_leaves_existing_file_untouched() -> Result<(), Box<dyn Error>> {
+        let archive = single_entry_archive("a.txt", b"new", 0)?;
+        let dest = std::env::temp_dir().join("wpress_oxide_test_overwrite");
+        let _ = remove_dir_all(&dest);
+        fs::create_dir_all(&dest)?;
+        write(dest.join("a.txt"), b"old")?;
+
+        let mut r = Reader::new(Cursor::new(archive))?;
+        r.set_overwrite(false);
+        r.extract_to(&dest)?;
+
+        assert_eq!(fs::read(dest.join("a.txt"))?, b"old");
+
+        remove_dir_all(&dest)?;
+        Ok(())
+    }
+
+    #[test]
+    fn append_extends_an_existing_archive_in_place() -> Result<(), Box<dyn Error>> {
+        let path = std::env::temp_dir().join("wpress_oxide_test_append.wpress");
+        let _ = remove_file(&path);
+
+        let mut w = Writer::create(&path)?;
+        w.add_data(
+            Header::new("a.txt".into(), 5, 0, "".into())?,
+            Cursor::new(b"hello".to_vec()),
+        );
+        w.write()?;
+
+        let mut w = Writer::append(&path)?;
+        w.add_data(
+            Header::new("b.txt".into(), 5, 0, "".into())?,
+            Cursor::new(b"world".to_vec()),
+        );
+        w.write()?;
+
+        let mut r = Reader::open(&path)?;
+        assert_eq!(r.files_count(), 2);
+        let mut entries = r.entries();
+
+        let (header, mut data) = entries.next().unwrap()?;
+        assert_eq!(header.name, "a.txt");
+        let mut buf = String::new();
+        data.read_to_string(&mut buf)?;
+        assert_eq!(buf, "hello");
+
+        let (header, mut data) = entries.next().unwrap()?;
+        assert_eq!(header.name, "b.txt");
+        let mut buf = String::new();
+        data.read_to_string(&mut buf)?;
+        assert_eq!(buf, "world");
+
+        assert!(entries.next().is_none());
+        drop(entries);
+        remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn verify_accepts_an_untampered_archive() -> Result<(), Box<dyn Error>> {
+        let mut archive = Vec::new();
+        let mut manifest = Vec::new();
+        let mut w = Writer::new(&mut archive);
+        w.add_data(
+            Header::new("a.txt".into(), 5, 0, "".into())?,
+            Cursor::new(b"hello".to_vec()),
+        );
+        w.write_with_manifest(&mut manifest)?;
+
+        let mut r = Reader::new(Cursor::new(archive))?;
+        let findings = r.verify(Cursor::new(manifest))?;
+        assert!(findings.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn verify_reports_a_mismatch_for_tampered_data() -> Result<(), Box<dyn Error>> {
+        let mut archive = Vec::new();
+        let mut manifest = Vec::new();
+        let mut w = Writer::new(&mut archive);
+        w.add_data(
+            Header::new("a.txt".into(), 5, 0, "".into())?,
+            Cursor::new(b"hello".to_vec()),
+        );
+        w.write_with_manifest(&mut manifest)?;
+
+        archive[crate::common::HEADER_SIZE] = b'!';
+
+        let mut r = Reader::new(Cursor::new(archive))?;
+        let findings = r.verify(Cursor::new(manifest))?;
+        assert_eq!(
+            findings,
+            vec![VerifyError::Mismatch {
+                name: "a.txt".to_string()
+            }]
+        );
+        Ok(())
+    }
 }
@@ -1,15 +1,25 @@
-use crate::common::{ExtractError, FileParseError, Header, HeaderError, EOF_BLOCK, HEADER_SIZE};
+use crate::common::{
+    entry_name, read_manifest, ExtractError, FileParseError, Header, HeaderError, VerifyError,
+    EOF_BLOCK, HEADER_SIZE,
+};
 use clean_path::Clean;
+use filetime::{set_file_mtime, FileTime};
+use sha2::{Digest, Sha256};
 use std::{
     fs::{create_dir_all, File},
     io::{self, Read, Seek, SeekFrom},
     path::{Path, PathBuf, StripPrefixError},
 };
 
-/// Structure that can read, parse and extract a wpress archive file.
-pub struct Reader {
-    file: std::fs::File,
+/// Structure that can read, parse and extract a wpress archive.
+///
+/// `Reader` is generic over any `R: Read + Seek`, so an archive can be parsed from a file, an
+/// in-memory buffer such as `Cursor<Vec<u8>>`, or any other seekable byte source.
+pub struct Reader<R> {
+    inner: R,
     headers: Vec<Header>,
+    preserve_mtime: bool,
+    overwrite: bool,
 }
 
 fn trim_clean<P: AsRef<Path>>(path: P) -> Result<PathBuf, StripPrefixError> {
@@ -20,14 +30,13 @@ fn trim_clean<P: AsRef<Path>>(path: P) -> Result<PathBuf, StripPrefixError> {
     Ok(cleaned)
 }
 
-impl Reader {
-    /// Creates a new `Reader` with the path supplied as the source file.
-    pub fn new<P: AsRef<Path>>(path: P) -> Result<Reader, FileParseError> {
-        let mut file = std::fs::File::open(path)?;
+impl<R: Read + Seek> Reader<R> {
+    /// Creates a new `Reader` wrapping the given source, parsing every header up front.
+    pub fn new(mut inner: R) -> Result<Reader<R>, FileParseError> {
         let mut headers = Vec::new();
         let mut buf = vec![0; HEADER_SIZE];
         loop {
-            if HEADER_SIZE != file.read(&mut buf)? {
+            if HEADER_SIZE != inner.read(&mut buf)? {
                 Err(FileParseError::Header(HeaderError::IncompleteHeader))?;
             }
             if EOF_BLOCK == buf {
@@ -36,9 +45,40 @@ impl Reader {
             let header = Header::from_bytes(&buf)?;
             let next_header = header.size as i64;
             headers.push(header);
-            file.seek(SeekFrom::Current(next_header))?;
+            inner.seek(SeekFrom::Current(next_header))?;
         }
-        Ok(Reader { file, headers })
+        Ok(Reader {
+            inner,
+            headers,
+            preserve_mtime: false,
+            overwrite: true,
+        })
+    }
+
+    /// Consumes the `Reader`, returning the underlying source.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Mutably borrows the underlying source, for subsystems (e.g. `fuse`) that need to seek and
+    /// read entry bytes on demand without taking ownership of the `Reader`.
+    #[cfg(feature = "fuse")]
+    pub(crate) fn inner_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Controls whether extraction restores each file's modification time from its
+    /// `Header::mtime`. Off by default, matching the historical behavior of `extract_to` and
+    /// `extract_file`.
+    pub fn set_preserve_mtime(&mut self, preserve_mtime: bool) {
+        self.preserve_mtime = preserve_mtime;
+    }
+
+    /// Controls whether extraction overwrites a file that already exists at the destination
+    /// path. On by default, matching the historical behavior of `extract_to` and `extract_file`.
+    /// When off, an already-existing destination path is left untouched and skipped.
+    pub fn set_overwrite(&mut self, overwrite: bool) {
+        self.overwrite = overwrite;
     }
 
     /// Extracts all the files inside the archive to the provided destination directory.
@@ -49,7 +89,7 @@ impl Reader {
     /// # use std::fs::remove_dir_all;
     /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// use wpress_oxide::Reader;
-    /// let mut r = Reader::new("tests/reader/archive.wpress")?;
+    /// let mut r = Reader::open("tests/reader/archive.wpress")?;
     /// r.extract_to("tests/reader_output_0")?;
     /// #    remove_dir_all("tests/reader_output_0")?;
     /// #    Ok(())
@@ -57,15 +97,22 @@ impl Reader {
     /// ```
     pub fn extract_to<P: AsRef<Path>>(&mut self, destination: P) -> Result<(), ExtractError> {
         let destination = destination.as_ref();
-        self.file.rewind()?;
+        self.inner.rewind()?;
         for header in self.headers.iter() {
-            self.file.seek(io::SeekFrom::Current(HEADER_SIZE as i64))?;
+            self.inner.seek(io::SeekFrom::Current(HEADER_SIZE as i64))?;
             let clean = trim_clean([&header.prefix, &header.name].iter().collect::<PathBuf>())?;
             let path = Path::new(destination).join(clean);
+            if !self.overwrite && path.exists() {
+                self.inner.seek(io::SeekFrom::Current(header.size as i64))?;
+                continue;
+            }
             let dir = path.parent().unwrap_or(Path::new(destination));
             create_dir_all(dir)?;
-            let mut handle = File::create(path)?;
-            io::copy(&mut (&mut self.file).take(header.size), &mut handle)?;
+            let mut handle = File::create(&path)?;
+            io::copy(&mut (&mut self.inner).take(header.size), &mut handle)?;
+            if self.preserve_mtime {
+                set_file_mtime(&path, FileTime::from_unix_time(header.mtime as i64, 0))?;
+            }
         }
         Ok(())
     }
@@ -77,7 +124,7 @@ impl Reader {
     /// ```
     /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// use wpress_oxide::Reader;
-    /// let mut r = Reader::new("tests/reader/archive.wpress")?;
+    /// let mut r = Reader::open("tests/reader/archive.wpress")?;
     /// r.extract()?;
     /// #    Ok(())
     /// # }
@@ -101,6 +148,62 @@ impl Reader {
         self.headers.clone()
     }
 
+    /// Returns a lazy, streaming view over the entries of the archive. Call `.next()` in a loop;
+    /// each item pairs a `&Header` with a `Read` handle bounded to exactly that entry's `size`
+    /// bytes, so a caller can stream a single file's contents into memory, pipe it elsewhere, or
+    /// inspect it without ever touching a destination directory.
+    ///
+    /// `Entries` is not a standard `Iterator`: each entry's reader borrows `Entries` itself, so
+    /// the borrow checker only allows one entry's reader to be alive at a time, which is what
+    /// makes it sound to hand out a `&mut` view of the same underlying source repeatedly.
+    pub fn entries(&mut self) -> Entries<'_, R> {
+        let mut offset = 0u64;
+        let offsets: Vec<u64> = self
+            .headers
+            .iter()
+            .map(|header| {
+                let start = offset;
+                offset += HEADER_SIZE as u64 + header.size;
+                start
+            })
+            .collect();
+        Entries {
+            inner: &mut self.inner,
+            headers: self.headers.iter().zip(offsets),
+        }
+    }
+
+    /// Re-reads every entry, recomputes its SHA-256 digest, and compares it against the checksum
+    /// sidecar produced by `Writer::write_with_manifest`. Returns every entry whose digest
+    /// doesn't match the recorded one, or for which the sidecar has no record at all.
+    pub fn verify<M: Read>(&mut self, manifest: M) -> io::Result<Vec<VerifyError>> {
+        let recorded = read_manifest(manifest)?;
+        let mut findings = Vec::new();
+        let mut entries = self.entries();
+        while let Some(item) = entries.next() {
+            let (header, mut data) = item?;
+            let name = entry_name(header);
+
+            let mut hasher = Sha256::new();
+            let mut buf = [0u8; 8192];
+            loop {
+                let read = data.read(&mut buf)?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&buf[..read]);
+            }
+            let digest = format!("{:x}", hasher.finalize());
+
+            match recorded.get(&name) {
+                Some(expected) if *expected == digest => {}
+                Some(_) => findings.push(VerifyError::Mismatch { name }),
+                None => findings.push(VerifyError::Missing { name }),
+            }
+        }
+        Ok(findings)
+    }
+
     /// Extract a single file, given either its name or *complete path inside the archive*, to a
     /// destination directory. Preserves the directory hierarchy of the archive during extraction.
     ///
@@ -112,7 +215,7 @@ impl Reader {
     /// # use std::fs::remove_dir_all;
     /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// use wpress_oxide::Reader;
-    /// let mut r = Reader::new("tests/reader/archive.wpress")?;
+    /// let mut r = Reader::open("tests/reader/archive.wpress")?;
     /// r.extract_file("file.txt", "tests/reader_output_1")?;
     /// #    remove_dir_all("tests/reader_output_1")?;
     /// #    Ok(())
@@ -125,7 +228,7 @@ impl Reader {
     /// # use std::fs::remove_dir_all;
     /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// use wpress_oxide::Reader;
-    /// let mut r = Reader::new("tests/reader/archive.wpress")?;
+    /// let mut r = Reader::open("tests/reader/archive.wpress")?;
     /// r.extract_file(
     ///     "tests/writer/directory/subdirectory/file.txt",
     ///     "tests/reader_output_2",
@@ -134,13 +237,12 @@ impl Reader {
     /// #    Ok(())
     /// # }
     /// ```
-
     pub fn extract_file<P: AsRef<Path>>(
         &mut self,
         filename: P,
         destination: P,
     ) -> Result<(), ExtractError> {
-        self.file.rewind()?;
+        self.inner.rewind()?;
         let mut offset = 0;
         let filename = filename.as_ref();
         let destination = destination.as_ref();
@@ -153,11 +255,16 @@ impl Reader {
             if Path::new(&header.name) == filename || clean == filename || original_path == filename
             {
                 let path = destination.join(clean);
-                let dir = path.parent().unwrap_or(destination);
-                create_dir_all(dir)?;
-                let mut handle = File::create(path)?;
-                self.file.seek(SeekFrom::Start(offset))?;
-                io::copy(&mut (&mut self.file).take(header.size), &mut handle)?;
+                if self.overwrite || !path.exists() {
+                    let dir = path.parent().unwrap_or(destination);
+                    create_dir_all(dir)?;
+                    let mut handle = File::create(&path)?;
+                    self.inner.seek(SeekFrom::Start(offset))?;
+                    io::copy(&mut (&mut self.inner).take(header.size), &mut handle)?;
+                    if self.preserve_mtime {
+                        set_file_mtime(&path, FileTime::from_unix_time(header.mtime as i64, 0))?;
+                    }
+                }
                 break;
             }
 
@@ -166,3 +273,40 @@ impl Reader {
         Ok(())
     }
 }
+
+impl Reader<File> {
+    /// Creates a new `Reader` with the path supplied as the source file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Reader<File>, FileParseError> {
+        Reader::new(File::open(path)?)
+    }
+}
+
+/// A streaming view over the entries of a [`Reader`], returned by [`Reader::entries`].
+///
+/// This is deliberately not a standard `Iterator`: an `Iterator`'s `Item` type is fixed
+/// independently of any particular call to `next`, which would force a returned
+/// `io::Take<&mut R>` to borrow `R` for the entire lifetime of `Entries` rather than just the
+/// call that produced it — letting safe code hold two live readers over the same mutable `R` at
+/// once (unsound). Calling `next(&mut self)` as an inherent method ties the returned reader to
+/// that one call's borrow of `self`, so the borrow checker rejects advancing to the next entry
+/// before the current one's reader is dropped.
+pub struct Entries<'a, R> {
+    inner: &'a mut R,
+    headers: std::iter::Zip<std::slice::Iter<'a, Header>, std::vec::IntoIter<u64>>,
+}
+
+impl<'a, R: Read + Seek> Entries<'a, R> {
+    /// Advances to the next entry, returning its header paired with a reader bounded to exactly
+    /// that entry's bytes. Returns `None` once every entry has been visited.
+    ///
+    /// Named `next` to read naturally at the call site, even though (as explained above) this is
+    /// deliberately not `Iterator::next`.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<io::Result<(&Header, io::Take<&mut R>)>> {
+        let (header, offset) = self.headers.next()?;
+        if let Err(e) = self.inner.seek(SeekFrom::Start(offset + HEADER_SIZE as u64)) {
+            return Some(Err(e));
+        }
+        Some(Ok((header, self.inner.take(header.size))))
+    }
+}
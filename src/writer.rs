@@ -1,30 +1,47 @@
 use crate::{
-    common::{ArchiveError, Header, EOF_BLOCK},
+    common::{entry_name, ArchiveError, Header, HeaderError, EOF_BLOCK, HEADER_SIZE},
     FileParseError,
 };
+use sha2::{Digest, Sha256};
 use std::{
-    fs::File,
-    io::{copy, Write},
+    fs::{File, OpenOptions},
+    io::{copy, Cursor, Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
 };
 
+/// An entry queued up in a `Writer`, either a filesystem path resolved lazily at `write` time or
+/// an already-built `Header` paired with an arbitrary byte stream.
+enum Source {
+    Path(PathBuf),
+    Data(Header, Box<dyn Read>),
+}
+
 /// Structure to write multiple files and corresponding metadata into a wpress archive.
-pub struct Writer {
-    file: std::fs::File,
-    paths: Vec<PathBuf>,
+///
+/// `Writer` is generic over any `W: Write`, so an archive can be built on top of a file, an
+/// in-memory buffer, a network socket, or any other byte sink.
+pub struct Writer<W> {
+    inner: W,
+    sources: Vec<Source>,
 }
-impl Writer {
-    /// Creates a new `Writer` with the destination being the path supplied.
-    pub fn new<P: AsRef<Path>>(path: P) -> Result<Writer, ArchiveError> {
-        Ok(Writer {
-            file: File::create(path).map_err(ArchiveError::FileCreation)?,
-            paths: vec![],
-        })
+
+impl<W: Write> Writer<W> {
+    /// Creates a new `Writer` wrapping the given destination.
+    pub fn new(inner: W) -> Writer<W> {
+        Writer {
+            inner,
+            sources: vec![],
+        }
+    }
+
+    /// Consumes the `Writer`, returning the underlying destination.
+    pub fn into_inner(self) -> W {
+        self.inner
     }
 
     /// Lazily adds paths to the `Writer`. It merely tells the `Writer` to note the supplied path
-    /// and does not write to the underlying file. To write to the underlying file, use the
-    /// `write` method after `add`ing all the files.
+    /// and does not write to the underlying destination. To write to the underlying destination,
+    /// use the `write` method after `add`ing all the files.
     pub fn add<P: AsRef<Path>>(&mut self, path: P) -> Result<(), ArchiveError> {
         let path = path.as_ref();
         // If the given path is a directory,
@@ -35,32 +52,150 @@ impl Writer {
                 self.add(entry.map_err(ArchiveError::EntryAddition)?.path())?;
             }
         } else if path.is_file() {
-            self.paths.push(path.to_path_buf());
+            self.sources.push(Source::Path(path.to_path_buf()));
         }
         // Do not add symbolic links or devices.
         Ok(())
     }
 
-    /// Writes header structures and associated data to the underlying file handle. Since the
-    /// object is consumed, the file is closed on drop, making sure we cannot incorrectly write
-    /// multiple times to the same file.
+    /// Adds an entry built from an arbitrary reader rather than a filesystem path, using the
+    /// `size` already recorded in `header`. Lets synthesized content, such as a generated SQL
+    /// dump or an in-memory config, become an archive member without ever touching disk. Build
+    /// `header` with `Header::new` when there's no real file to read metadata from.
+    pub fn add_data<D: Read + 'static>(&mut self, header: Header, data: D) {
+        self.sources.push(Source::Data(header, Box::new(data)));
+    }
+
+    /// Like `add_data`, but for a reader whose length isn't known up front: `data` is buffered in
+    /// full to measure its size, which then backfills `header`'s size field before it's queued.
+    pub fn add_data_unsized<D: Read>(
+        &mut self,
+        mut header: Header,
+        mut data: D,
+    ) -> Result<(), ArchiveError> {
+        let mut buf = Vec::new();
+        data.read_to_end(&mut buf)
+            .map_err(ArchiveError::EntryAddition)?;
+        header
+            .set_size(buf.len() as u64)
+            .map_err(FileParseError::from)?;
+        self.sources
+            .push(Source::Data(header, Box::new(Cursor::new(buf))));
+        Ok(())
+    }
+
+    /// Writes header structures and associated data to the underlying destination. Since the
+    /// object is consumed, the destination is dropped afterwards (which closes it when it is a
+    /// `File`), so the same `Writer` cannot write its entries twice.
     pub fn write(mut self) -> Result<(), ArchiveError> {
-        for path in self.paths.iter() {
-            let header = Header::from_file_metadata(path)?;
-            let mut handle = File::open(path).map_err(FileParseError::FileRead)?;
-            self.file
-                .write_all(&header.bytes)
+        for source in self.sources.iter_mut() {
+            match source {
+                Source::Path(path) => {
+                    let header = Header::from_file(path.as_path())?;
+                    let mut handle = File::open(path.as_path()).map_err(FileParseError::FileRead)?;
+                    self.inner
+                        .write_all(&header.bytes)
+                        .map_err(ArchiveError::FileWrite)?;
+                    copy(&mut handle, &mut self.inner).map_err(ArchiveError::FileWrite)?;
+                }
+                Source::Data(header, data) => {
+                    self.inner
+                        .write_all(&header.bytes)
+                        .map_err(ArchiveError::FileWrite)?;
+                    copy(data, &mut self.inner).map_err(ArchiveError::FileWrite)?;
+                }
+            }
+        }
+        // This marks the end of the file.
+        self.inner
+            .write_all(EOF_BLOCK)
+            .map_err(ArchiveError::FileWrite)?;
+        Ok(())
+    }
+
+    /// Like `write`, but additionally computes a SHA-256 digest of each entry's bytes as they are
+    /// copied, recording `path -> digest` pairs to `manifest` as one `name\tdigest` line per
+    /// entry. Because the on-disk `.wpress` header layout is fixed at `HEADER_SIZE` and can't
+    /// carry extra fields without breaking compatibility, the digests live in this external
+    /// sidecar rather than the archive itself; pair with `Reader::verify` to detect corruption.
+    pub fn write_with_manifest<M: Write>(mut self, mut manifest: M) -> Result<(), ArchiveError> {
+        for source in self.sources.iter_mut() {
+            let mut file_storage: Option<File>;
+            let (header_bytes, name, handle): (Vec<u8>, String, &mut dyn Read) = match source {
+                Source::Path(path) => {
+                    let header = Header::from_file(path.as_path())?;
+                    let name = entry_name(&header);
+                    file_storage =
+                        Some(File::open(path.as_path()).map_err(FileParseError::FileRead)?);
+                    (header.bytes, name, file_storage.as_mut().unwrap())
+                }
+                Source::Data(header, data) => {
+                    (header.bytes.clone(), entry_name(header), data.as_mut())
+                }
+            };
+            self.inner
+                .write_all(&header_bytes)
                 .map_err(ArchiveError::FileWrite)?;
-            copy(&mut handle, &mut self.file).map_err(ArchiveError::FileWrite)?;
+
+            let mut hasher = Sha256::new();
+            let mut buf = [0u8; 8192];
+            loop {
+                let read = handle.read(&mut buf).map_err(ArchiveError::FileWrite)?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&buf[..read]);
+                self.inner
+                    .write_all(&buf[..read])
+                    .map_err(ArchiveError::FileWrite)?;
+            }
+            writeln!(manifest, "{name}\t{:x}", hasher.finalize()).map_err(ArchiveError::FileWrite)?;
         }
         // This marks the end of the file.
-        self.file
+        self.inner
             .write_all(EOF_BLOCK)
             .map_err(ArchiveError::FileWrite)?;
         Ok(())
     }
 
     pub fn files_count(&self) -> usize {
-        self.paths.len()
+        self.sources.len()
+    }
+}
+
+impl Writer<File> {
+    /// Creates a new `Writer` with the destination being the path supplied.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Writer<File>, ArchiveError> {
+        Ok(Writer::new(
+            File::create(path).map_err(ArchiveError::FileCreation)?,
+        ))
+    }
+
+    /// Opens an existing archive for appending further entries to it, rather than rebuilding it
+    /// from scratch. Scans forward header-by-header until it reaches the terminating
+    /// `EOF_BLOCK`, then rewinds just before it so that newly `add`ed entries are written in
+    /// place, followed by a fresh `EOF_BLOCK`.
+    pub fn append<P: AsRef<Path>>(path: P) -> Result<Writer<File>, ArchiveError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .map_err(ArchiveError::FileCreation)?;
+        let mut buf = vec![0; HEADER_SIZE];
+        loop {
+            let terminator = file.stream_position().map_err(ArchiveError::FileWrite)?;
+            if HEADER_SIZE != file.read(&mut buf).map_err(ArchiveError::FileWrite)? {
+                Err(FileParseError::Header(HeaderError::IncompleteHeader))?;
+            }
+            if buf == EOF_BLOCK {
+                file.seek(SeekFrom::Start(terminator))
+                    .map_err(ArchiveError::FileWrite)?;
+                break;
+            }
+            let header = Header::from_bytes(&buf).map_err(FileParseError::from)?;
+            file.seek(SeekFrom::Current(header.size as i64))
+                .map_err(ArchiveError::FileWrite)?;
+        }
+        Ok(Writer::new(file))
     }
 }